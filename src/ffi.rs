@@ -4,7 +4,7 @@
 
 // Copyright 2019 Joyent, Inc.
 
-use std::os::raw::{c_char, c_int};
+use std::os::raw::{c_char, c_int, c_uint, c_void};
 
 #[repr(C)]
 /// illumos opaque type priv_set_t
@@ -34,6 +34,26 @@ extern "C" {
     pub fn priv_isemptyset(sp: *mut OpaquePrivSet) -> c_int;
     /// checks whether the privilege set src is equal to dst.
     pub fn priv_isequalset(src: *const OpaquePrivSet, dst: *const OpaquePrivSet) -> c_int;
+    /// Computes the union of src and dst, and stores the result in dst.
+    pub fn priv_union(src: *const OpaquePrivSet, dst: *mut OpaquePrivSet);
+    /// Computes the intersection of src and dst, and stores the result in dst.
+    pub fn priv_intersect(src: *const OpaquePrivSet, dst: *mut OpaquePrivSet);
+    /// Replaces sp with its complement within the universal set of privileges.
+    pub fn priv_inverse(sp: *mut OpaquePrivSet);
+    /// Copies the privilege set src to dst.
+    pub fn priv_copyset(src: *const OpaquePrivSet, dst: *mut OpaquePrivSet) -> c_int;
+    /// Parses buf, a sep-separated list of privilege names (optionally prefixed with `!` to
+    /// remove, or one of the special tokens "basic", "all", "none", "zone"), into a newly
+    /// allocated privilege set. Returns NULL and sets errno on failure; on a parse error endptr
+    /// is set to the offending token.
+    pub fn priv_str_to_set(
+        buf: *const c_char,
+        sep: *const c_char,
+        endptr: *mut *const c_char,
+    ) -> *mut OpaquePrivSet;
+    /// Renders sp as a sep-separated, nul-terminated string. The caller must free the returned
+    /// buffer with free(3C).
+    pub fn priv_set_to_str(sp: *const OpaquePrivSet, sep: c_char, flags: c_int) -> *mut c_char;
 
     /// Sets or changes the process privilege set. The op argument specifies the operation and can
     /// be one of PRIV_OFF, PRIV_ON or PRIV_SET. The which argument specifies the name of the
@@ -43,4 +63,29 @@ extern "C" {
     /// memory for set is allocated with priv_allocset() and freed with priv_freeset(). Both
     /// functions are documented on the priv_addset(3C) manual page.
     pub fn getppriv(which: *const c_char, set: *mut OpaquePrivSet) -> c_int;
+
+    /// Frees a buffer allocated by libc, such as the string returned by priv_set_to_str().
+    pub fn free(ptr: *mut c_void);
+
+    /// Sets or clears the per-process privilege flag named by flag. val is 0 to clear the flag
+    /// or 1 to set it.
+    pub fn setpflags(flag: c_uint, val: c_uint) -> c_int;
+    /// Returns 1 if the per-process privilege flag named by flag is set, 0 otherwise.
+    pub fn getpflags(flag: c_uint) -> c_uint;
 }
+
+// The PRIV_* / NET_MAC_AWARE* flag values accepted by setpflags(2)/getpflags(2) are #define'd
+// constants in <sys/priv.h>, not symbols, so unlike the priv_* functions above they can't be
+// bound by name and linked against directly. They're reproduced here from that header so there
+// is exactly one place in the crate that needs checking against a newer <sys/priv.h> if illumos
+// ever renumbers them.
+/// PRIV_DEBUG, from `<sys/priv.h>`. See `setpflags(2)`.
+pub const PRIV_DEBUG: c_uint = 0x0001;
+/// PRIV_PFEXEC, from `<sys/priv.h>`. See `setpflags(2)`.
+pub const PRIV_PFEXEC: c_uint = 0x0100;
+/// NET_MAC_AWARE, from `<sys/priv.h>`. See `setpflags(2)`.
+pub const NET_MAC_AWARE: c_uint = 0x0010;
+/// NET_MAC_AWARE_INHERIT, from `<sys/priv.h>`. See `setpflags(2)`.
+pub const NET_MAC_AWARE_INHERIT: c_uint = 0x0020;
+/// PRIV_XPOLICY, from `<sys/priv.h>`. See `setpflags(2)`.
+pub const PRIV_XPOLICY: c_uint = 0x0080;
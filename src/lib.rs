@@ -31,15 +31,21 @@
 //!
 //! ```
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
+use std::fmt;
 use std::io;
 use std::os::raw::c_char;
+use std::ptr;
 
 mod ffi;
+mod pflags;
 mod privileges;
+mod profile;
 
 // Have to use "crate::" here due to a bug in rust 1.31 which is used by jenkins
+pub use crate::pflags::{get_pflags, set_pflags, PrivFlag};
 pub use crate::privileges::Privilege;
+pub use crate::profile::{PrivAction, PrivName, PrivProfile, PrivProfileEntry};
 
 /// See GETPPRIV(2) for more in depth documentation.
 pub enum PrivPtype {
@@ -108,6 +114,22 @@ impl PrivSet {
         }
     }
 
+    /// Allocates a new `PrivSet` parsed from `spec`, a comma- or space-separated list of
+    /// privilege names, optionally prefixed with `!` to mean "remove", plus the special tokens
+    /// `basic`, `all`, `none` and `zone` (see `priv_str_to_set(3C)`). For example
+    /// `"basic,!proc_fork,net_access"` starts from the basic set, removes `proc_fork` and adds
+    /// `net_access`.
+    pub fn from_str_spec(spec: &str) -> io::Result<Self> {
+        let spec = str_to_cstring(spec)?;
+        let sep = CStr::from_bytes_with_nul(b", \0").expect("literal is nul terminated");
+        unsafe {
+            let mut endptr: *const c_char = ptr::null();
+            let inner = ffi::priv_str_to_set(spec.as_ptr(), sep.as_ptr(), &mut endptr);
+            let inner = ptr_or_err(inner)?;
+            Ok(PrivSet { inner })
+        }
+    }
+
     /// Adds the "basic" set to the `PrivSet`.
     pub fn basic(&mut self) {
         unsafe {
@@ -146,6 +168,63 @@ impl PrivSet {
     pub fn is_equal(&self, dst: &PrivSet) -> bool {
         unsafe { true_or_false(ffi::priv_isequalset(self.inner, dst.inner)) }
     }
+
+    /// Adds every privilege in `other` to this `PrivSet`.
+    pub fn union(&mut self, other: &PrivSet) {
+        unsafe { ffi::priv_union(other.inner, self.inner) }
+    }
+
+    /// Removes every privilege from this `PrivSet` that is not also present in `other`.
+    pub fn intersect(&mut self, other: &PrivSet) {
+        unsafe { ffi::priv_intersect(other.inner, self.inner) }
+    }
+
+    /// Replaces this `PrivSet` with its complement, i.e. every privilege not currently a member
+    /// becomes a member and vice versa.
+    pub fn inverse(&mut self) {
+        unsafe { ffi::priv_inverse(self.inner) }
+    }
+
+    /// Copies the contents of `src` into this `PrivSet`, replacing whatever it previously held.
+    pub fn copy_from(&mut self, src: &PrivSet) -> io::Result<()> {
+        unsafe { ret_or_err(ffi::priv_copyset(src.inner, self.inner)) }
+    }
+
+    /// Adds the named privilege to the `PrivSet`. Unlike [`PrivSet::addset`], `name` is not
+    /// restricted to the [`Privilege`] enum, which lets callers reach privileges the enum
+    /// doesn't (yet) know about. Returns an error if the kernel doesn't recognize `name`.
+    pub fn addset_str(&mut self, name: &str) -> io::Result<()> {
+        let name = str_to_cstring(name)?;
+        unsafe { ret_or_err(ffi::priv_addset(self.inner, name.as_ptr())) }
+    }
+
+    /// Removes the named privilege from the `PrivSet`. See [`PrivSet::addset_str`] for why this
+    /// takes an arbitrary `&str` instead of a [`Privilege`].
+    pub fn delset_str(&mut self, name: &str) -> io::Result<()> {
+        let name = str_to_cstring(name)?;
+        unsafe { ret_or_err(ffi::priv_delset(self.inner, name.as_ptr())) }
+    }
+
+    /// Determines whether the named privilege is a member of the `PrivSet`. See
+    /// [`PrivSet::addset_str`] for why this takes an arbitrary `&str` instead of a [`Privilege`].
+    /// Unlike [`PrivSet::is_member`], this returns an error if the kernel doesn't recognize
+    /// `name`, since unlike the closed [`Privilege`] enum an arbitrary name can fail to resolve.
+    pub fn is_member_str(&self, name: &str) -> io::Result<bool> {
+        let name = str_to_cstring(name)?;
+        unsafe { ret_or_bool(ffi::priv_ismember(self.inner, name.as_ptr())) }
+    }
+
+    /// Renders the `PrivSet` as a comma-separated, human-readable string of privilege names,
+    /// suitable for logging or diagnostics. This is the inverse of [`PrivSet::from_str_spec`].
+    pub fn to_spec(&self) -> io::Result<String> {
+        unsafe {
+            let raw = ffi::priv_set_to_str(self.inner, b',' as c_char, 0);
+            let raw = ptr_or_err(raw)?;
+            let spec = CStr::from_ptr(raw).to_string_lossy().into_owned();
+            ffi::free(raw as *mut std::os::raw::c_void);
+            Ok(spec)
+        }
+    }
 }
 
 impl PartialEq for PrivSet {
@@ -154,6 +233,20 @@ impl PartialEq for PrivSet {
     }
 }
 
+impl Clone for PrivSet {
+    fn clone(&self) -> Self {
+        let mut dst = PrivSet::new_empty().expect("failed to allocate privilege set");
+        dst.copy_from(self).expect("failed to copy privilege set");
+        dst
+    }
+}
+
+impl fmt::Display for PrivSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_spec().map_err(|_| fmt::Error)?)
+    }
+}
+
 /// Sets or changes the processes privilege set.
 pub fn setppriv(op: PrivOp, ptype: PrivPtype, sp: &PrivSet) -> io::Result<()> {
     unsafe { ret_or_err(ffi::setppriv(op as i32, ptype.as_ptr(), sp.inner)) }
@@ -171,6 +264,46 @@ pub fn getppriv(ptype: PrivPtype) -> io::Result<PrivSet> {
     }
 }
 
+/// Restricts the calling process's four privilege sets to at most the sets given, in one call.
+///
+/// This is the "waive privileges" pattern used when a process is entering a sandboxed or
+/// minimal-privilege state: rather than issuing four separate [`setppriv`] calls that each
+/// replace a set outright (and can therefore fail if, say, the new limit set no longer permits
+/// what the current effective set holds), `restrict` only ever *removes* privileges. For each of
+/// `limit`, `permitted`, `effective` and `inheritable` (in that order) it clones the desired set,
+/// inverts it to get "everything not wanted", and issues `setppriv(PRIV_OFF, ...)` with the
+/// inverted set. `PRIV_OFF` can only clear bits that are already permitted by the limit set, so
+/// applying limit first guarantees the remaining three calls never ask to drop a privilege that
+/// the limit set has already removed, and the whole sequence cannot fail for privilege reasons.
+///
+/// Because of this, `restrict` can only ever *remove* privileges from the calling process, never
+/// add them: if `effective`, `permitted`, `inheritable` or `limit` names a privilege the process
+/// doesn't currently hold, that privilege is silently left absent rather than an error being
+/// returned. `Ok(())` means the four sets no longer contain anything outside of what was asked
+/// for, not that they contain everything that was asked for — callers that need the latter
+/// should check the resulting sets (e.g. via [`getppriv`]) after calling this.
+pub fn restrict(
+    effective: &PrivSet,
+    permitted: &PrivSet,
+    inheritable: &PrivSet,
+    limit: &PrivSet,
+) -> io::Result<()> {
+    let wanted = [
+        (PrivPtype::Limit, limit),
+        (PrivPtype::Permitted, permitted),
+        (PrivPtype::Effective, effective),
+        (PrivPtype::Inheritable, inheritable),
+    ];
+
+    for (ptype, desired) in wanted {
+        let mut drop_set = desired.clone();
+        drop_set.inverse();
+        setppriv(PrivOp::Off, ptype, &drop_set)?;
+    }
+
+    Ok(())
+}
+
 impl Drop for PrivSet {
     fn drop(&mut self) {
         if !self.inner.is_null() {
@@ -191,13 +324,24 @@ fn ptr_or_err<T>(ptr: *mut T) -> io::Result<*mut T> {
     }
 }
 
-fn ret_or_err(ret: i32) -> io::Result<()> {
+pub(crate) fn ret_or_err(ret: i32) -> io::Result<()> {
     match ret {
         -1 => Err(io::Error::last_os_error()),
         _ => Ok(()),
     }
 }
 
+fn ret_or_bool(ret: i32) -> io::Result<bool> {
+    match ret {
+        -1 => Err(io::Error::last_os_error()),
+        ret => Ok(ret == 1),
+    }
+}
+
+fn str_to_cstring(s: &str) -> io::Result<CString> {
+    CString::new(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
 fn true_or_false(ret: i32) -> bool {
     /*
      * Jenkins builds with rust 1.4.0, which doesn't support the matches!()
@@ -294,6 +438,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn union_test() {
+        let mut set = PrivSet::new_empty().unwrap();
+        let mut other = PrivSet::new_empty().unwrap();
+        other.addset(Privilege::ProcFork).unwrap();
+        set.union(&other);
+        assert_eq!(true, set.is_member(Privilege::ProcFork), "union added PRIV_PROC_FORK");
+    }
+
+    #[test]
+    fn intersect_test() {
+        let mut set = PrivSet::new_basic().unwrap();
+        let mut other = PrivSet::new_empty().unwrap();
+        other.addset(Privilege::ProcFork).unwrap();
+        set.intersect(&other);
+        assert_eq!(
+            true,
+            set.is_member(Privilege::ProcFork),
+            "PRIV_PROC_FORK survived the intersection"
+        );
+        assert_eq!(
+            false,
+            set.is_member(Privilege::ProcExec),
+            "PRIV_PROC_EXEC did not survive the intersection"
+        );
+    }
+
+    #[test]
+    fn inverse_test() {
+        let mut set = PrivSet::new_empty().unwrap();
+        set.addset(Privilege::ProcFork).unwrap();
+        set.inverse();
+        assert_eq!(
+            false,
+            set.is_member(Privilege::ProcFork),
+            "PRIV_PROC_FORK is no longer in the set"
+        );
+    }
+
+    #[test]
+    fn copy_from_test() {
+        let src = PrivSet::new_basic().unwrap();
+        let mut dst = PrivSet::new_empty().unwrap();
+        dst.copy_from(&src).expect("failed to copy privilege set");
+        assert!(src == dst, "copy_from produces an equal PrivSet");
+    }
+
+    #[test]
+    fn clone_test() {
+        let src = PrivSet::new_basic().unwrap();
+        let dst = src.clone();
+        assert!(src == dst, "clone produces an equal PrivSet");
+    }
+
+    #[test]
+    fn addset_str_test() {
+        let mut set = PrivSet::new_empty().unwrap();
+        assert_eq!(
+            false,
+            set.is_member_str("proc_fork").unwrap(),
+            "PRIV_PROC_FORK is not in the set"
+        );
+        set.addset_str("proc_fork")
+            .expect("failed to add to the set by name");
+        assert_eq!(
+            true,
+            set.is_member_str("proc_fork").unwrap(),
+            "PRIV_PROC_FORK is in the set"
+        );
+    }
+
+    #[test]
+    fn delset_str_test() {
+        let mut set = PrivSet::new_basic().unwrap();
+        set.delset_str("proc_fork")
+            .expect("failed to delete from the set by name");
+        assert_eq!(
+            false,
+            set.is_member_str("proc_fork").unwrap(),
+            "PRIV_PROC_FORK is not in the set"
+        );
+    }
+
+    #[test]
+    fn addset_str_unknown_test() {
+        let mut set = PrivSet::new_empty().unwrap();
+        assert!(
+            set.addset_str("not_a_real_privilege").is_err(),
+            "unknown privilege name is rejected"
+        );
+    }
+
+    #[test]
+    fn is_member_str_unknown_test() {
+        let set = PrivSet::new_empty().unwrap();
+        assert!(
+            set.is_member_str("not_a_real_privilege").is_err(),
+            "unknown privilege name is rejected"
+        );
+    }
+
+    #[test]
+    fn from_str_spec_test() {
+        let set = PrivSet::from_str_spec("basic,!proc_fork,net_access")
+            .expect("failed to parse privilege spec");
+        assert_eq!(false, set.is_member(Privilege::ProcFork), "proc_fork was removed");
+        assert_eq!(
+            true,
+            set.is_member_str("net_access").unwrap(),
+            "net_access was added"
+        );
+    }
+
+    #[test]
+    fn from_str_spec_space_separated_test() {
+        let set = PrivSet::from_str_spec("basic !proc_fork net_access")
+            .expect("failed to parse privilege spec");
+        assert_eq!(false, set.is_member(Privilege::ProcFork), "proc_fork was removed");
+        assert_eq!(
+            true,
+            set.is_member_str("net_access").unwrap(),
+            "net_access was added"
+        );
+    }
+
+    #[test]
+    fn to_spec_roundtrip_test() {
+        let src = PrivSet::new_basic().unwrap();
+        let spec = src.to_spec().expect("failed to render privilege spec");
+        let dst = PrivSet::from_str_spec(&spec).expect("failed to parse rendered spec");
+        assert!(src == dst, "to_spec output round-trips through from_str_spec");
+    }
+
+    #[test]
+    fn display_test() {
+        let set = PrivSet::new_basic().unwrap();
+        assert_eq!(set.to_spec().unwrap(), format!("{}", set));
+    }
+
     #[test]
     fn getppriv_test() {
         let orig = getppriv(PrivPtype::Effective).unwrap();
@@ -309,6 +592,115 @@ mod tests {
         setppriv(PrivOp::Set, PrivPtype::Effective, &orig).unwrap();
     }
 
+    #[test]
+    fn restrict_test() {
+        let orig_effective = getppriv(PrivPtype::Effective).unwrap();
+        let orig_permitted = getppriv(PrivPtype::Permitted).unwrap();
+        let orig_inheritable = getppriv(PrivPtype::Inheritable).unwrap();
+        let orig_limit = getppriv(PrivPtype::Limit).unwrap();
+
+        let mut set = PrivSet::new_basic().unwrap();
+        set.delset(Privilege::ProcFork)
+            .expect("failed to delete from set");
+
+        // Only the effective set is narrowed: once lowered, the limit and permitted sets can never
+        // be raised again, so they (and the inheritable set) are passed through unchanged.
+        restrict(&set, &orig_permitted, &orig_inheritable, &orig_limit)
+            .expect("failed to restrict privileges");
+
+        let effective = getppriv(PrivPtype::Effective).unwrap();
+        assert!(
+            set == effective,
+            "effective set matches the one passed to restrict"
+        );
+
+        // Reset the original privilege sets so other tests don't fail. Only the effective set
+        // changed; setting the other three back to their original values leaves them as they are.
+        setppriv(PrivOp::Set, PrivPtype::Limit, &orig_limit).unwrap();
+        setppriv(PrivOp::Set, PrivPtype::Permitted, &orig_permitted).unwrap();
+        setppriv(PrivOp::Set, PrivPtype::Effective, &orig_effective).unwrap();
+        setppriv(PrivOp::Set, PrivPtype::Inheritable, &orig_inheritable).unwrap();
+    }
+
+    #[test]
+    fn pflags_roundtrip_test() {
+        let orig = get_pflags(PrivFlag::PfExec);
+        set_pflags(PrivFlag::PfExec, !orig).expect("failed to set pflag");
+        assert_eq!(!orig, get_pflags(PrivFlag::PfExec), "pflag was flipped");
+        // Reset so other tests don't observe a changed flag.
+        set_pflags(PrivFlag::PfExec, orig).expect("failed to reset pflag");
+    }
+
+    #[test]
+    fn profile_permitted_defaults_to_effective_test() {
+        let profile = PrivProfile::from_entries(&[(
+            PrivPtype::Effective,
+            Privilege::ProcFork.into(),
+            PrivAction::Add,
+        )])
+        .expect("failed to build profile");
+        assert!(
+            profile.permitted() == profile.effective(),
+            "permitted defaults to the effective set"
+        );
+    }
+
+    #[test]
+    fn profile_limit_defaults_to_union_test() {
+        let profile = PrivProfile::from_entries(&[
+            (PrivPtype::Effective, Privilege::ProcFork.into(), PrivAction::Add),
+            (PrivPtype::Inheritable, "net_access".into(), PrivAction::Add),
+        ])
+        .expect("failed to build profile");
+        assert!(
+            profile.limit().is_member(Privilege::ProcFork),
+            "limit includes everything from effective"
+        );
+        assert!(
+            profile
+                .limit()
+                .is_member_str("net_access")
+                .expect("failed to check membership"),
+            "limit includes everything from inheritable"
+        );
+    }
+
+    #[test]
+    fn profile_explicit_permitted_and_limit_are_kept_test() {
+        let profile = PrivProfile::from_entries(&[
+            (PrivPtype::Effective, Privilege::ProcFork.into(), PrivAction::Add),
+            (PrivPtype::Permitted, Privilege::ProcFork.into(), PrivAction::Add),
+            (PrivPtype::Permitted, Privilege::ProcExec.into(), PrivAction::Add),
+            (PrivPtype::Limit, Privilege::ProcFork.into(), PrivAction::Add),
+            (PrivPtype::Limit, Privilege::ProcExec.into(), PrivAction::Add),
+            (PrivPtype::Limit, "net_access".into(), PrivAction::Add),
+        ])
+        .expect("failed to build profile");
+        assert!(
+            profile.permitted().is_member(Privilege::ProcExec),
+            "explicit permitted entries are kept as given"
+        );
+        assert!(
+            profile
+                .limit()
+                .is_member_str("net_access")
+                .expect("failed to check membership"),
+            "explicit limit entries are kept as given"
+        );
+    }
+
+    #[test]
+    fn profile_validate_rejects_set_outside_limit_test() {
+        let result = PrivProfile::from_entries(&[
+            (PrivPtype::Effective, Privilege::ProcFork.into(), PrivAction::Add),
+            (PrivPtype::Limit, Privilege::ProcExec.into(), PrivAction::Add),
+        ]);
+        assert!(
+            result.is_err(),
+            "effective set exceeds the explicit limit set"
+        );
+    }
+
     #[test]
     fn drop_fork_test() {
         let orig = getppriv(PrivPtype::Effective).unwrap();
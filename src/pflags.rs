@@ -0,0 +1,58 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Copyright 2021 Joyent, Inc.
+
+//! Per-process privilege flags, set and read via `setpflags(2)`/`getpflags(2)`. These are
+//! distinct from the privilege *sets* the rest of this crate manipulates: a flag controls
+//! process-wide privilege-related behavior (e.g. whether the process is debugged when it's
+//! denied a privilege, or whether it honors `pfexec(1)` profiles) rather than which privileges
+//! are held.
+
+use std::io;
+
+use crate::ffi;
+
+/// A per-process privilege flag. See `PRIVILEGES(5)` and `setpflags(2)` for the authoritative
+/// description of each flag.
+pub enum PrivFlag {
+    /// `PRIV_DEBUG`: causes the kernel to send `SIGTRAP` when the process is denied access to a
+    /// privileged operation because it lacks the privilege, making it easier to find the missing
+    /// privilege.
+    Debug,
+    /// `PRIV_XPOLICY`: causes the kernel to apply the "extended" privilege escalation policy
+    /// when the process invokes a set-uid-root or set-gid executable.
+    XPolicy,
+    /// `PRIV_PFEXEC`: enables profile-based execution, allowing the process to gain additional
+    /// privileges for the commands listed in its `exec_attr(5)` profile.
+    PfExec,
+    /// `NET_MAC_AWARE`: the process is aware of, and honors, Multi-Level Desktop / Trusted
+    /// Extensions MAC labeling for the sockets it creates.
+    NetMacAware,
+    /// `NET_MAC_AWARE_INHERIT`: like `NetMacAware`, but the setting is inherited across `exec`.
+    NetMacAwareInherit,
+}
+
+impl PrivFlag {
+    fn as_raw(&self) -> u32 {
+        match self {
+            PrivFlag::Debug => ffi::PRIV_DEBUG,
+            PrivFlag::PfExec => ffi::PRIV_PFEXEC,
+            PrivFlag::NetMacAware => ffi::NET_MAC_AWARE,
+            PrivFlag::NetMacAwareInherit => ffi::NET_MAC_AWARE_INHERIT,
+            PrivFlag::XPolicy => ffi::PRIV_XPOLICY,
+        }
+    }
+}
+
+/// Sets or clears `flag` for the calling process.
+pub fn set_pflags(flag: PrivFlag, on: bool) -> io::Result<()> {
+    let val = if on { 1 } else { 0 };
+    unsafe { crate::ret_or_err(ffi::setpflags(flag.as_raw(), val)) }
+}
+
+/// Returns whether `flag` is currently set for the calling process.
+pub fn get_pflags(flag: PrivFlag) -> bool {
+    unsafe { ffi::getpflags(flag.as_raw()) != 0 }
+}
@@ -0,0 +1,171 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Copyright 2021 Joyent, Inc.
+
+//! A table-driven way to describe the four privilege sets a process (or one of several
+//! subprocess roles) should run with, instead of a hand-rolled sequence of `addset`/`delset`/
+//! `setppriv` calls scattered across a daemon's startup code.
+//!
+//! A [`PrivProfile`] is built from a flat list of `(PrivPtype, name, PrivAction)` entries, where
+//! `name` is anything that identifies a privilege -- a [`Privilege`] or a plain `&str` for
+//! privileges the enum doesn't cover. The permitted set defaults to a superset of the effective
+//! set, and the limit set defaults to the union of effective, permitted and inheritable, mirroring
+//! how these sets are related in practice; either can be overridden by listing explicit entries
+//! for them. [`PrivProfile::apply`] hands the four resulting sets to [`crate::restrict`] so a role
+//! is always entered atomically.
+
+use std::io;
+
+use crate::{PrivPtype, PrivSet, Privilege};
+
+/// Whether a [`PrivProfile`] entry adds or removes the named privilege from its set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivAction {
+    /// Add the privilege to the set.
+    Add,
+    /// Remove the privilege from the set.
+    Remove,
+}
+
+/// The name of a privilege in a [`PrivProfile`] entry: either one of the enumerated
+/// [`Privilege`] variants, or an arbitrary name for a privilege the enum doesn't know about.
+#[derive(Debug, Clone)]
+pub enum PrivName {
+    /// A privilege from the fixed [`Privilege`] enum.
+    Enum(Privilege),
+    /// An arbitrary privilege name, passed through to [`PrivSet::addset_str`]/
+    /// [`PrivSet::delset_str`].
+    Named(String),
+}
+
+impl PrivName {
+    fn apply(&self, set: &mut PrivSet, action: PrivAction) -> io::Result<()> {
+        match (self, action) {
+            (PrivName::Enum(p), PrivAction::Add) => set.addset(*p),
+            (PrivName::Enum(p), PrivAction::Remove) => set.delset(*p),
+            (PrivName::Named(s), PrivAction::Add) => set.addset_str(s),
+            (PrivName::Named(s), PrivAction::Remove) => set.delset_str(s),
+        }
+    }
+}
+
+impl From<Privilege> for PrivName {
+    fn from(p: Privilege) -> Self {
+        PrivName::Enum(p)
+    }
+}
+
+impl From<&str> for PrivName {
+    fn from(s: &str) -> Self {
+        PrivName::Named(s.to_string())
+    }
+}
+
+/// One entry in a [`PrivProfile`]'s table: add or remove `name` from the given privilege set.
+pub type PrivProfileEntry = (PrivPtype, PrivName, PrivAction);
+
+/// The four privilege sets a role should run with, computed from a table of entries and ready to
+/// be applied with [`PrivProfile::apply`].
+pub struct PrivProfile {
+    effective: PrivSet,
+    permitted: PrivSet,
+    inheritable: PrivSet,
+    limit: PrivSet,
+}
+
+impl PrivProfile {
+    /// Builds a `PrivProfile` from a table of entries. If no entry targets
+    /// [`PrivPtype::Permitted`], the permitted set defaults to the effective set. If no entry
+    /// targets [`PrivPtype::Limit`], the limit set defaults to the union of the effective,
+    /// permitted and inheritable sets. The resulting profile is validated so that the effective,
+    /// permitted and inheritable sets are each contained in the limit set before it's returned.
+    pub fn from_entries(entries: &[PrivProfileEntry]) -> io::Result<Self> {
+        let mut effective = PrivSet::new_empty()?;
+        let mut permitted = PrivSet::new_empty()?;
+        let mut inheritable = PrivSet::new_empty()?;
+        let mut limit = PrivSet::new_empty()?;
+        let mut has_permitted = false;
+        let mut has_limit = false;
+
+        for (ptype, name, action) in entries {
+            let set = match ptype {
+                PrivPtype::Effective => &mut effective,
+                PrivPtype::Permitted => {
+                    has_permitted = true;
+                    &mut permitted
+                }
+                PrivPtype::Inheritable => &mut inheritable,
+                PrivPtype::Limit => {
+                    has_limit = true;
+                    &mut limit
+                }
+            };
+            name.apply(set, *action)?;
+        }
+
+        if !has_permitted {
+            permitted.union(&effective);
+        }
+        if !has_limit {
+            limit.union(&effective);
+            limit.union(&permitted);
+            limit.union(&inheritable);
+        }
+
+        let profile = PrivProfile {
+            effective,
+            permitted,
+            inheritable,
+            limit,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Checks that the effective, permitted and inheritable sets are each contained in the limit
+    /// set, which must hold for [`PrivProfile::apply`] to produce the sets it was asked to.
+    pub fn validate(&self) -> io::Result<()> {
+        for set in [&self.effective, &self.permitted, &self.inheritable] {
+            let mut within_limit = set.clone();
+            within_limit.intersect(&self.limit);
+            if !(within_limit == *set) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a PrivProfile set holds a privilege outside of its limit set",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Atomically applies this profile's four sets to the calling process via [`crate::restrict`].
+    ///
+    /// Like `restrict`, this can only remove privileges the process currently holds, not add
+    /// ones it lacks: a role whose entries name a privilege the process never had is silently
+    /// short of that privilege rather than erroring on `apply`.
+    pub fn apply(&self) -> io::Result<()> {
+        crate::restrict(&self.effective, &self.permitted, &self.inheritable, &self.limit)
+    }
+
+    /// The effective set this profile was built with.
+    pub fn effective(&self) -> &PrivSet {
+        &self.effective
+    }
+
+    /// The permitted set this profile was built with.
+    pub fn permitted(&self) -> &PrivSet {
+        &self.permitted
+    }
+
+    /// The inheritable set this profile was built with.
+    pub fn inheritable(&self) -> &PrivSet {
+        &self.inheritable
+    }
+
+    /// The limit set this profile was built with.
+    pub fn limit(&self) -> &PrivSet {
+        &self.limit
+    }
+}